@@ -0,0 +1,352 @@
+//! The `VCALENDAR` container: a whole `.ics` file's worth of `VEVENT`,
+//! `VTODO`, `VJOURNAL` and `VTIMEZONE` components, typed the same way as
+//! [`crate::Event`].
+
+use std::io::BufRead;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+use ical::parser::ical::component::{
+    IcalCalendar, IcalJournal, IcalTimeZone, IcalTimeZoneTransitionType, IcalTodo,
+};
+
+use crate::{map_ical_event, parse_datetime, DateMaybeTime, Event};
+
+/// Matches the `METHOD` property of a `VCALENDAR`.
+#[derive(Debug)]
+pub enum CalendarMethod {
+    /// Publish an event, todo or journal entry; no reply expected.
+    Publish,
+    /// Request attendance, updates or a reply.
+    Request,
+    /// Reply to a REQUEST.
+    Reply,
+    /// Cancel a previously published or requested component.
+    Cancel,
+}
+
+impl FromStr for CalendarMethod {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "PUBLISH" => Ok(CalendarMethod::Publish),
+            "REQUEST" => Ok(CalendarMethod::Request),
+            "REPLY" => Ok(CalendarMethod::Reply),
+            "CANCEL" => Ok(CalendarMethod::Cancel),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Matches the `STATUS` property of a `VTODO`.
+#[derive(Debug)]
+pub enum TodoStatus {
+    /// Not yet started.
+    NeedsAction,
+    /// Currently being worked on.
+    InProcess,
+    /// Finished.
+    Completed,
+    /// Abandoned.
+    Cancelled,
+}
+
+impl FromStr for TodoStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "NEEDS-ACTION" => Ok(TodoStatus::NeedsAction),
+            "IN-PROCESS" => Ok(TodoStatus::InProcess),
+            "COMPLETED" => Ok(TodoStatus::Completed),
+            "CANCELLED" => Ok(TodoStatus::Cancelled),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A `VTODO` task.
+#[derive(Debug, Default)]
+pub struct Todo {
+    /// Matches UID.
+    pub uid: Option<String>,
+    /// Matches SUMMARY.
+    pub summary: Option<String>,
+    /// Matches DESCRIPTION.
+    pub description: Option<String>,
+    /// Matches DUE.
+    pub due: Option<DateMaybeTime>,
+    /// Matches COMPLETED.
+    pub completed: Option<DateMaybeTime>,
+    /// Matches PERCENT-COMPLETE.
+    pub percent_complete: Option<u8>,
+    /// Matches STATUS.
+    pub status: Option<TodoStatus>,
+}
+
+fn parse_ical_todo(input: &IcalTodo, tz_offsets: &[(String, i32)]) -> Result<Todo, Error> {
+    let mut todo = Todo::default();
+    for prop in input.properties.iter() {
+        let Some(value) = prop.value.as_ref() else {
+            continue;
+        };
+        match prop.name.as_str() {
+            "UID" => todo.uid = Some(value.to_string()),
+            "SUMMARY" => todo.summary = Some(value.to_string()),
+            "DESCRIPTION" => todo.description = Some(value.to_string()),
+            "DUE" => todo.due = Some(parse_datetime(value, prop.params.as_ref(), tz_offsets)?),
+            "COMPLETED" => {
+                todo.completed = Some(parse_datetime(value, prop.params.as_ref(), tz_offsets)?)
+            }
+            "PERCENT-COMPLETE" => {
+                todo.percent_complete = Some(
+                    value
+                        .parse()
+                        .map_err(|_| anyhow!("Invalid percent-complete"))?,
+                )
+            }
+            "STATUS" => {
+                todo.status = Some(value.parse().map_err(|_| anyhow!("Invalid todo status"))?)
+            }
+            _ => (),
+        }
+    }
+    Ok(todo)
+}
+
+/// Matches the `STATUS` property of a `VJOURNAL`.
+#[derive(Debug)]
+pub enum JournalStatus {
+    /// A work-in-progress draft.
+    Draft,
+    /// Finalized.
+    Final,
+    /// Retracted.
+    Cancelled,
+}
+
+impl FromStr for JournalStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "DRAFT" => Ok(JournalStatus::Draft),
+            "FINAL" => Ok(JournalStatus::Final),
+            "CANCELLED" => Ok(JournalStatus::Cancelled),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A `VJOURNAL` entry.
+#[derive(Debug, Default)]
+pub struct Journal {
+    /// Matches UID.
+    pub uid: Option<String>,
+    /// Matches SUMMARY.
+    pub summary: Option<String>,
+    /// Matches DESCRIPTION.
+    pub description: Option<String>,
+    /// Matches DTSTART.
+    pub start: Option<DateMaybeTime>,
+    /// Matches STATUS.
+    pub status: Option<JournalStatus>,
+}
+
+fn parse_ical_journal(input: &IcalJournal, tz_offsets: &[(String, i32)]) -> Result<Journal, Error> {
+    let mut journal = Journal::default();
+    for prop in input.properties.iter() {
+        let Some(value) = prop.value.as_ref() else {
+            continue;
+        };
+        match prop.name.as_str() {
+            "UID" => journal.uid = Some(value.to_string()),
+            "SUMMARY" => journal.summary = Some(value.to_string()),
+            "DESCRIPTION" => journal.description = Some(value.to_string()),
+            "DTSTART" => {
+                journal.start = Some(parse_datetime(value, prop.params.as_ref(), tz_offsets)?)
+            }
+            "STATUS" => {
+                journal.status = Some(
+                    value
+                        .parse()
+                        .map_err(|_| anyhow!("Invalid journal status"))?,
+                )
+            }
+            _ => (),
+        }
+    }
+    Ok(journal)
+}
+
+/// A single `STANDARD`/`DAYLIGHT` transition rule within a `VTIMEZONE`.
+#[derive(Debug)]
+pub struct TimezoneTransition {
+    /// Whether this is a `DAYLIGHT` (as opposed to `STANDARD`) transition.
+    pub daylight: bool,
+    /// Matches TZOFFSETFROM, in seconds.
+    pub offset_from: Option<i32>,
+    /// Matches TZOFFSETTO, in seconds.
+    pub offset_to: Option<i32>,
+}
+
+/// An in-file `VTIMEZONE` definition, used as a fallback when a `TZID`
+/// cannot be resolved against the IANA database.
+#[derive(Debug, Default)]
+pub struct TimezoneDefinition {
+    /// Matches TZID.
+    pub tzid: Option<String>,
+    /// The `STANDARD`/`DAYLIGHT` transition rules making up this zone.
+    pub transitions: Vec<TimezoneTransition>,
+}
+
+fn parse_utc_offset(s: &str) -> Option<i32> {
+    let (sign, rest) = match s.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, s.strip_prefix('+').unwrap_or(s)),
+    };
+    if rest.len() < 4 {
+        return None;
+    }
+    let hours: i32 = rest.get(0..2)?.parse().ok()?;
+    let minutes: i32 = rest.get(2..4)?.parse().ok()?;
+    let seconds: i32 = rest.get(4..6).and_then(|s| s.parse().ok()).unwrap_or(0);
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+fn parse_ical_timezone(input: &IcalTimeZone) -> TimezoneDefinition {
+    let mut timezone = TimezoneDefinition::default();
+    for prop in input.properties.iter() {
+        if prop.name == "TZID" {
+            timezone.tzid = prop.value.clone();
+        }
+    }
+    for transition in input.transitions.iter() {
+        let daylight = matches!(transition.transition, IcalTimeZoneTransitionType::DAYLIGHT);
+        let mut offset_from = None;
+        let mut offset_to = None;
+        for prop in transition.properties.iter() {
+            let Some(value) = prop.value.as_ref() else {
+                continue;
+            };
+            match prop.name.as_str() {
+                "TZOFFSETFROM" => offset_from = parse_utc_offset(value),
+                "TZOFFSETTO" => offset_to = parse_utc_offset(value),
+                _ => (),
+            }
+        }
+        timezone.transitions.push(TimezoneTransition {
+            daylight,
+            offset_from,
+            offset_to,
+        });
+    }
+    timezone
+}
+
+/// The `VCALENDAR` container, holding the parsed top-level properties plus
+/// all its `VEVENT`, `VTODO`, `VJOURNAL` and `VTIMEZONE` components.
+#[derive(Debug, Default)]
+pub struct Calendar {
+    /// Matches PRODID.
+    pub prodid: Option<String>,
+    /// Matches VERSION.
+    pub version: Option<String>,
+    /// Matches METHOD.
+    pub method: Option<CalendarMethod>,
+    /// The `VEVENT` components.
+    pub events: Vec<Event>,
+    /// The `VTODO` components.
+    pub todos: Vec<Todo>,
+    /// The `VJOURNAL` components.
+    pub journals: Vec<Journal>,
+    /// The `VTIMEZONE` components, keyed by nothing in particular; look up
+    /// by `tzid` via [`Calendar::find_timezone`].
+    pub timezones: Vec<TimezoneDefinition>,
+}
+
+impl Calendar {
+    /// Find an in-file `VTIMEZONE` definition by its `TZID`, for use as a
+    /// fallback when the zone isn't in the IANA database.
+    pub fn find_timezone(&self, tzid: &str) -> Option<&TimezoneDefinition> {
+        self.timezones
+            .iter()
+            .find(|tz| tz.tzid.as_deref() == Some(tzid))
+    }
+}
+
+impl TimezoneDefinition {
+    /// A single representative UTC offset for this zone, used as a
+    /// fallback when its `TZID` can't be resolved against the IANA
+    /// database. Prefers the last `STANDARD` transition (closest to
+    /// "current" for most real-world files), falling back to the last
+    /// transition of any kind.
+    fn offset_seconds(&self) -> Option<i32> {
+        self.transitions
+            .iter()
+            .rev()
+            .find(|t| !t.daylight)
+            .or_else(|| self.transitions.last())
+            .and_then(|t| t.offset_to)
+    }
+}
+
+impl TryFrom<&IcalCalendar> for Calendar {
+    type Error = Error;
+
+    fn try_from(value: &IcalCalendar) -> Result<Self, Self::Error> {
+        let mut calendar = Calendar::default();
+
+        for prop in value.properties.iter() {
+            let Some(prop_value) = prop.value.as_ref() else {
+                continue;
+            };
+            match prop.name.as_str() {
+                "PRODID" => calendar.prodid = Some(prop_value.to_string()),
+                "VERSION" => calendar.version = Some(prop_value.to_string()),
+                "METHOD" => {
+                    calendar.method =
+                        Some(prop_value.parse().map_err(|_| anyhow!("Invalid method"))?)
+                }
+                _ => (),
+            }
+        }
+
+        for timezone in value.timezones.iter() {
+            calendar.timezones.push(parse_ical_timezone(timezone));
+        }
+        // Resolved before the components below so a DTSTART/DUE/etc. whose
+        // TZID isn't in the IANA database can still fall back to the
+        // calendar's own VTIMEZONE-declared offset instead of being read in
+        // this machine's local zone.
+        let tz_offsets: Vec<(String, i32)> = calendar
+            .timezones
+            .iter()
+            .filter_map(|tz| Some((tz.tzid.clone()?, tz.offset_seconds()?)))
+            .collect();
+
+        for event in value.events.iter() {
+            calendar.events.push(map_ical_event(event, &tz_offsets)?);
+        }
+        for todo in value.todos.iter() {
+            calendar.todos.push(parse_ical_todo(todo, &tz_offsets)?);
+        }
+        for journal in value.journals.iter() {
+            calendar
+                .journals
+                .push(parse_ical_journal(journal, &tz_offsets)?);
+        }
+
+        Ok(calendar)
+    }
+}
+
+impl Calendar {
+    /// Parse every `VCALENDAR` found in `reader` at once.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Vec<Calendar>, Error> {
+        ical::IcalParser::new(reader)
+            .map(|calendar| Calendar::try_from(&calendar?))
+            .collect()
+    }
+}
@@ -1,13 +1,125 @@
 #![deny(missing_docs)]
 #![doc = include_str!("../README.md")]
 
+pub mod calendar;
+pub mod query;
+
 use anyhow::{anyhow, Error};
-use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, Utc};
-use ical::parser::ical::component::IcalEvent;
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use ical::parser::ical::component::{IcalAlarm, IcalEvent};
+use ical::property::Property;
 use regex::Regex;
 use rrule::RRuleSet;
 use std::str::FromStr;
 
+/// Ical property parameters as produced by the `ical` crate: a list of
+/// `(NAME, [VALUE, ...])` pairs, e.g. `[("TZID", ["Europe/Berlin"])]`.
+pub(crate) type PropParams = Vec<(String, Vec<String>)>;
+
+/// Look up the first value of a named parameter, e.g. `TZID` or `VALUE`.
+pub(crate) fn find_param<'a>(params: Option<&'a PropParams>, name: &str) -> Option<&'a str> {
+    params?
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .and_then(|(_, values)| values.first())
+        .map(|v| v.as_str())
+}
+
+/// Resolve a floating (zone-less) local datetime in `tz` to a `DateTime<Utc>`.
+/// On an ambiguous wall-clock time (DST "fall back") the earlier of the two
+/// instants is used; on a non-existent one (DST "spring forward" gap) the
+/// earliest valid instant after the gap is used.
+fn resolve_local_datetime<Z: TimeZone>(tz: Z, naive: NaiveDateTime) -> DateTime<Utc> {
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(dt) => dt.to_utc(),
+        LocalResult::Ambiguous(earliest, _latest) => earliest.to_utc(),
+        LocalResult::None => {
+            let mut candidate = naive;
+            loop {
+                candidate += Duration::minutes(1);
+                match tz.from_local_datetime(&candidate) {
+                    LocalResult::Single(dt) => break dt.to_utc(),
+                    LocalResult::Ambiguous(earliest, _) => break earliest.to_utc(),
+                    LocalResult::None => continue,
+                }
+            }
+        }
+    }
+}
+
+/// Maximum length, in octets, of a content line before it must be folded,
+/// as mandated by RFC 5545 section 3.1.
+const FOLD_LIMIT: usize = 75;
+
+/// Fold a single unfolded content line into CRLF + space continuations.
+fn fold_line(line: &str) -> String {
+    let mut out = String::new();
+    let mut current_len = 0;
+    for c in line.chars() {
+        let clen = c.len_utf8();
+        if current_len + clen > FOLD_LIMIT {
+            out.push_str("\r\n ");
+            current_len = 1;
+        }
+        out.push(c);
+        current_len += clen;
+    }
+    out.push_str("\r\n");
+    out
+}
+
+/// Escape `,`, `;`, `\` and newlines as required for ical TEXT values.
+fn escape_text(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+/// Format a single ical parameter value, DQUOTE-quoting it (per RFC 5545
+/// §3.2) when it contains a `,`, `;` or `:` that would otherwise make the
+/// parameter list ambiguous to parse. Unlike TEXT property values, param
+/// values are quoted rather than backslash-escaped.
+fn quote_param_value(v: &str) -> String {
+    if v.contains(',') || v.contains(';') || v.contains(':') {
+        format!("\"{v}\"")
+    } else {
+        v.to_string()
+    }
+}
+
+fn format_duration(duration: &Duration) -> String {
+    let sign = if duration.num_seconds() < 0 { "-" } else { "" };
+    let mut secs = duration.num_seconds().abs();
+    let days = secs / 86400;
+    secs %= 86400;
+    let hours = secs / 3600;
+    secs %= 3600;
+    let minutes = secs / 60;
+    let seconds = secs % 60;
+
+    let mut out = format!("{sign}P");
+    if days > 0 {
+        out += &format!("{days}D");
+    }
+    if hours > 0 || minutes > 0 || seconds > 0 || days == 0 {
+        out += "T";
+        if hours > 0 {
+            out += &format!("{hours}H");
+        }
+        if minutes > 0 {
+            out += &format!("{minutes}M");
+        }
+        if seconds > 0 || (days == 0 && hours == 0 && minutes == 0) {
+            out += &format!("{seconds}S");
+        }
+    }
+    out
+}
+
 trait OptionVecPush<T> {
     fn push(&mut self, t: T);
 }
@@ -24,7 +136,7 @@ impl<T> OptionVecPush<T> for Option<Vec<T>> {
 
 /// Events can either happen at a date
 /// or a date time.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum DateMaybeTime {
     /// Event with a date and time
     DateTime(DateTime<Utc>),
@@ -44,9 +156,39 @@ impl From<DateTime<Utc>> for DateMaybeTime {
     }
 }
 
+impl DateMaybeTime {
+    /// Render as the `PARAMS:VALUE` part of an ical content line,
+    /// e.g. `VALUE=DATE:20240101` or `:20240101T090000Z`.
+    fn to_ical_param_value(&self) -> String {
+        match self {
+            DateMaybeTime::Date(d) => format!("VALUE=DATE:{}", d.format("%Y%m%d")),
+            DateMaybeTime::DateTime(dt) => format!(":{}", dt.format("%Y%m%dT%H%M%SZ")),
+        }
+    }
+
+    fn to_ical_line(&self, name: &str) -> String {
+        match self.to_ical_param_value().split_once(':') {
+            Some((params, value)) if !params.is_empty() => {
+                format!("{name};{params}:{value}")
+            }
+            Some((_, value)) => format!("{name}:{value}"),
+            None => unreachable!(),
+        }
+    }
+
+    /// The instant this date(time) refers to, taking midnight UTC for a
+    /// date-only value.
+    pub(crate) fn to_utc(&self) -> DateTime<Utc> {
+        match self {
+            DateMaybeTime::Date(d) => d.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+            DateMaybeTime::DateTime(dt) => *dt,
+        }
+    }
+}
+
 /// When inviting others, an
 /// Event can be tentative, confirmed or cancelled.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum EventStatus {
     /// Invite was not confirmed.
     Tentative,
@@ -91,9 +233,174 @@ impl FromStr for EventTransparency {
     }
 }
 
+/// The part an attendee plays in a meeting, matches the `ROLE` parameter.
+#[derive(Debug)]
+pub enum AttendeeRole {
+    /// Chairs the meeting.
+    Chair,
+    /// Participation is required.
+    ReqParticipant,
+    /// Participation is optional.
+    OptParticipant,
+    /// Copied for information only, not expected to attend.
+    NonParticipant,
+}
+
+impl FromStr for AttendeeRole {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "CHAIR" => Ok(AttendeeRole::Chair),
+            "REQ-PARTICIPANT" => Ok(AttendeeRole::ReqParticipant),
+            "OPT-PARTICIPANT" => Ok(AttendeeRole::OptParticipant),
+            "NON-PARTICIPANT" => Ok(AttendeeRole::NonParticipant),
+            _ => Err(()),
+        }
+    }
+}
+
+impl AttendeeRole {
+    fn as_ical_str(&self) -> &'static str {
+        match self {
+            AttendeeRole::Chair => "CHAIR",
+            AttendeeRole::ReqParticipant => "REQ-PARTICIPANT",
+            AttendeeRole::OptParticipant => "OPT-PARTICIPANT",
+            AttendeeRole::NonParticipant => "NON-PARTICIPANT",
+        }
+    }
+}
+
+/// An attendee's answer to an invitation, matches the `PARTSTAT` parameter.
+#[derive(Debug)]
+pub enum ParticipationStatus {
+    /// Has not yet responded.
+    NeedsAction,
+    /// Accepted the invitation.
+    Accepted,
+    /// Declined the invitation.
+    Declined,
+    /// Tentatively accepted the invitation.
+    Tentative,
+    /// Delegated attendance to someone else.
+    Delegated,
+}
+
+impl FromStr for ParticipationStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "NEEDS-ACTION" => Ok(ParticipationStatus::NeedsAction),
+            "ACCEPTED" => Ok(ParticipationStatus::Accepted),
+            "DECLINED" => Ok(ParticipationStatus::Declined),
+            "TENTATIVE" => Ok(ParticipationStatus::Tentative),
+            "DELEGATED" => Ok(ParticipationStatus::Delegated),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ParticipationStatus {
+    fn as_ical_str(&self) -> &'static str {
+        match self {
+            ParticipationStatus::NeedsAction => "NEEDS-ACTION",
+            ParticipationStatus::Accepted => "ACCEPTED",
+            ParticipationStatus::Declined => "DECLINED",
+            ParticipationStatus::Tentative => "TENTATIVE",
+            ParticipationStatus::Delegated => "DELEGATED",
+        }
+    }
+}
+
+/// A `CAL-ADDRESS` value (an `ATTENDEE` or `ORGANIZER`), together with the
+/// scheduling parameters ical carries alongside it.
+#[derive(Debug)]
+pub struct CalAddress {
+    /// The `mailto:` address, with the scheme stripped.
+    pub mailto: String,
+    /// Matches the `CN` parameter.
+    pub common_name: Option<String>,
+    /// Matches the `ROLE` parameter.
+    pub role: Option<AttendeeRole>,
+    /// Matches the `PARTSTAT` parameter.
+    pub partstat: Option<ParticipationStatus>,
+    /// Matches the `RSVP` parameter.
+    pub rsvp: bool,
+    /// Matches the `CUTYPE` parameter.
+    pub cu_type: Option<String>,
+}
+
+fn parse_cal_address(value: &str, params: Option<&PropParams>) -> CalAddress {
+    let mailto = value
+        .strip_prefix("mailto:")
+        .or_else(|| value.strip_prefix("MAILTO:"))
+        .unwrap_or(value)
+        .to_string();
+
+    CalAddress {
+        mailto,
+        common_name: find_param(params, "CN").map(|s| s.to_string()),
+        role: find_param(params, "ROLE").and_then(|s| s.parse().ok()),
+        partstat: find_param(params, "PARTSTAT").and_then(|s| s.parse().ok()),
+        rsvp: find_param(params, "RSVP").is_some_and(|s| s.eq_ignore_ascii_case("TRUE")),
+        cu_type: find_param(params, "CUTYPE").map(|s| s.to_string()),
+    }
+}
+
+/// Build the `NAME=VALUE` parameter list shared by the textual and
+/// structured (`Property`) serializations of a [`CalAddress`].
+fn cal_address_params(addr: &CalAddress) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    if let Some(cn) = &addr.common_name {
+        params.push(("CN".to_string(), cn.clone()));
+    }
+    if let Some(role) = &addr.role {
+        params.push(("ROLE".to_string(), role.as_ical_str().to_string()));
+    }
+    if let Some(partstat) = &addr.partstat {
+        params.push(("PARTSTAT".to_string(), partstat.as_ical_str().to_string()));
+    }
+    if addr.rsvp {
+        params.push(("RSVP".to_string(), "TRUE".to_string()));
+    }
+    if let Some(cu_type) = &addr.cu_type {
+        params.push(("CUTYPE".to_string(), cu_type.clone()));
+    }
+    params
+}
+
+fn cal_address_to_ical_line(name: &str, addr: &CalAddress) -> String {
+    let params = cal_address_params(addr);
+    let value = format!("mailto:{}", addr.mailto);
+    if params.is_empty() {
+        format!("{name}:{value}")
+    } else {
+        let params_str = params
+            .iter()
+            .map(|(k, v)| format!("{k}={}", quote_param_value(v)))
+            .collect::<Vec<_>>()
+            .join(";");
+        format!("{name};{params_str}:{value}")
+    }
+}
+
+fn cal_address_property(name: &str, addr: &CalAddress) -> Property {
+    let params = cal_address_params(addr);
+    Property {
+        name: name.to_string(),
+        params: if params.is_empty() {
+            None
+        } else {
+            Some(params.into_iter().map(|(k, v)| (k, vec![v])).collect())
+        },
+        value: Some(format!("mailto:{}", addr.mailto)),
+    }
+}
+
 fn parse_duration(s: &str) -> Result<Duration, Error> {
     let re = Regex::new(
-                r"^P(?:(?P<days>\d+)D)?(?:T(?:(?P<hours>\d+)H)?(?:(?P<minutes>\d+)M)?(?:(?P<seconds>\d+)S)?)?$",
+                r"^(?P<sign>[+-])?P(?:(?P<days>\d+)D)?(?:T(?:(?P<hours>\d+)H)?(?:(?P<minutes>\d+)M)?(?:(?P<seconds>\d+)S)?)?$",
             ).unwrap();
 
     if let Some(captures) = re.captures(s) {
@@ -114,16 +421,221 @@ fn parse_duration(s: &str) -> Result<Duration, Error> {
             .map(|m| m.as_str().parse::<i64>().unwrap_or(0))
             .unwrap_or(0);
 
-        Ok(Duration::days(days)
+        let duration = Duration::days(days)
             + Duration::hours(hours)
             + Duration::minutes(minutes)
-            + Duration::seconds(seconds))
+            + Duration::seconds(seconds);
+
+        Ok(if captures.name("sign").map(|m| m.as_str()) == Some("-") {
+            -duration
+        } else {
+            duration
+        })
     } else {
         Err(anyhow!("Invalid duration format"))
     }
 }
 
-fn parse_datetime(s: &str) -> Result<DateMaybeTime, Error> {
+/// What kind of notification a [`VALARM`](Alarm) raises, matches `ACTION`.
+#[derive(Debug)]
+pub enum AlarmAction {
+    /// Pop up a message.
+    Display,
+    /// Play a sound.
+    Audio,
+    /// Send an email.
+    Email,
+}
+
+impl FromStr for AlarmAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "DISPLAY" => Ok(AlarmAction::Display),
+            "AUDIO" => Ok(AlarmAction::Audio),
+            "EMAIL" => Ok(AlarmAction::Email),
+            _ => Err(()),
+        }
+    }
+}
+
+impl AlarmAction {
+    fn as_ical_str(&self) -> &'static str {
+        match self {
+            AlarmAction::Display => "DISPLAY",
+            AlarmAction::Audio => "AUDIO",
+            AlarmAction::Email => "EMAIL",
+        }
+    }
+}
+
+/// When a [`VALARM`](Alarm) fires, matches `TRIGGER`.
+#[derive(Debug)]
+pub enum AlarmTrigger {
+    /// Fires `duration` relative to the event's `DTSTART` (or `DTEND`
+    /// when `related_end` is set, via `RELATED=END`). A negative
+    /// duration means "before start"/"before end".
+    Relative {
+        /// Offset from the related anchor; negative means "before".
+        duration: Duration,
+        /// Whether the offset is relative to `DTEND` rather than `DTSTART`.
+        related_end: bool,
+    },
+    /// Fires at an absolute point in time.
+    Absolute(DateTime<Utc>),
+}
+
+fn parse_alarm_trigger(
+    value: &str,
+    params: Option<&PropParams>,
+    tz_offsets: &[(String, i32)],
+) -> Result<AlarmTrigger, Error> {
+    let is_absolute = find_param(params, "VALUE")
+        .is_some_and(|v| v.eq_ignore_ascii_case("DATE-TIME"))
+        || value.ends_with('Z');
+
+    if is_absolute {
+        return match parse_datetime(value, params, tz_offsets)? {
+            DateMaybeTime::DateTime(dt) => Ok(AlarmTrigger::Absolute(dt)),
+            DateMaybeTime::Date(d) => Ok(AlarmTrigger::Absolute(
+                d.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+            )),
+        };
+    }
+
+    Ok(AlarmTrigger::Relative {
+        duration: parse_duration(value)?,
+        related_end: find_param(params, "RELATED").is_some_and(|v| v.eq_ignore_ascii_case("END")),
+    })
+}
+
+fn alarm_trigger_to_ical_line(trigger: &AlarmTrigger) -> String {
+    match trigger {
+        AlarmTrigger::Absolute(dt) => {
+            format!("TRIGGER;VALUE=DATE-TIME:{}", dt.format("%Y%m%dT%H%M%SZ"))
+        }
+        AlarmTrigger::Relative {
+            duration,
+            related_end,
+        } => {
+            let value = format_duration(duration);
+            if *related_end {
+                format!("TRIGGER;RELATED=END:{value}")
+            } else {
+                format!("TRIGGER:{value}")
+            }
+        }
+    }
+}
+
+fn alarm_trigger_property(trigger: &AlarmTrigger) -> Property {
+    match trigger {
+        AlarmTrigger::Absolute(dt) => Property {
+            name: "TRIGGER".to_string(),
+            params: Some(vec![("VALUE".to_string(), vec!["DATE-TIME".to_string()])]),
+            value: Some(dt.format("%Y%m%dT%H%M%SZ").to_string()),
+        },
+        AlarmTrigger::Relative {
+            duration,
+            related_end,
+        } => Property {
+            name: "TRIGGER".to_string(),
+            params: related_end.then(|| vec![("RELATED".to_string(), vec!["END".to_string()])]),
+            value: Some(format_duration(duration)),
+        },
+    }
+}
+
+/// A `VALARM` reminder attached to an event.
+#[derive(Debug)]
+pub struct Alarm {
+    /// Matches ACTION.
+    pub action: AlarmAction,
+    /// Matches TRIGGER.
+    pub trigger: AlarmTrigger,
+    /// Matches DESCRIPTION.
+    pub description: Option<String>,
+    /// Matches REPEAT.
+    pub repeat: Option<u32>,
+    /// Matches DURATION, the interval between repeats.
+    pub repeat_interval: Option<Duration>,
+}
+
+fn parse_alarm(input: &IcalAlarm, tz_offsets: &[(String, i32)]) -> Result<Alarm, Error> {
+    let mut action = None;
+    let mut trigger = None;
+    let mut description = None;
+    let mut repeat = None;
+    let mut repeat_interval = None;
+
+    for prop in input.properties.iter() {
+        let Some(value) = prop.value.as_ref() else {
+            continue;
+        };
+        match prop.name.as_str() {
+            "ACTION" => action = Some(value.parse().map_err(|_| anyhow!("Invalid alarm action"))?),
+            "TRIGGER" => {
+                trigger = Some(parse_alarm_trigger(
+                    value,
+                    prop.params.as_ref(),
+                    tz_offsets,
+                )?)
+            }
+            "DESCRIPTION" => description = Some(value.to_string()),
+            "REPEAT" => {
+                repeat = Some(
+                    value
+                        .parse()
+                        .map_err(|_| anyhow!("Invalid alarm repeat count"))?,
+                )
+            }
+            "DURATION" => repeat_interval = Some(parse_duration(value)?),
+            _ => (),
+        }
+    }
+
+    Ok(Alarm {
+        action: action.ok_or_else(|| anyhow!("VALARM missing ACTION"))?,
+        trigger: trigger.ok_or_else(|| anyhow!("VALARM missing TRIGGER"))?,
+        description,
+        repeat,
+        repeat_interval,
+    })
+}
+
+fn alarm_to_ical(alarm: &Alarm) -> IcalAlarm {
+    let mut properties = vec![
+        simple_property("ACTION", alarm.action.as_ical_str().to_string()),
+        alarm_trigger_property(&alarm.trigger),
+    ];
+    if let Some(description) = &alarm.description {
+        properties.push(simple_property("DESCRIPTION", description.clone()));
+    }
+    if let Some(repeat) = alarm.repeat {
+        properties.push(simple_property("REPEAT", repeat.to_string()));
+    }
+    if let Some(repeat_interval) = &alarm.repeat_interval {
+        properties.push(simple_property(
+            "DURATION",
+            format_duration(repeat_interval),
+        ));
+    }
+    IcalAlarm { properties }
+}
+
+/// Parse a `DATE`/`DATE-TIME` property value, resolving a `TZID` param
+/// against (in order) the IANA database, then `tz_offsets` — named,
+/// fixed-offset fallbacks gathered from a calendar's own in-file
+/// `VTIMEZONE` definitions for zones that aren't in the IANA database
+/// (e.g. Microsoft's `W. Europe Standard Time`) — then finally this
+/// machine's local zone, matching the behavior for floating times.
+/// Pass an empty slice when no enclosing `VCALENDAR` is available.
+pub(crate) fn parse_datetime(
+    s: &str,
+    params: Option<&PropParams>,
+    tz_offsets: &[(String, i32)],
+) -> Result<DateMaybeTime, Error> {
     if let Ok(d) = NaiveDate::parse_from_str(s, "%Y%m%d") {
         return Ok(d.into());
     }
@@ -131,7 +643,28 @@ fn parse_datetime(s: &str) -> Result<DateMaybeTime, Error> {
     if let Ok(dt) = naive_datetime_res {
         return Ok(dt.and_utc().into());
     }
-    // No DateTime given, assume local
+
+    if let Some(tzid) = find_param(params, "TZID") {
+        let naive = NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S")?;
+        if let Ok(tz) = tzid.parse::<Tz>() {
+            return Ok(resolve_local_datetime(tz, naive).into());
+        }
+        if let Some((_, offset_seconds)) = tz_offsets.iter().find(|(id, _)| id == tzid) {
+            let offset = FixedOffset::east_opt(*offset_seconds)
+                .ok_or_else(|| anyhow!("Invalid VTIMEZONE offset for {}", tzid))?;
+            let dt = naive
+                .and_local_timezone(offset)
+                .single()
+                .ok_or_else(|| anyhow!("Ambiguous local time for TZID {}", tzid))?;
+            return Ok(dt.with_timezone(&Utc).into());
+        }
+        // Neither the IANA database nor an in-file VTIMEZONE resolved this
+        // TZID; fall back to this machine's local zone rather than
+        // aborting the whole parse.
+        return Ok(resolve_local_datetime(Local, naive).into());
+    }
+
+    // No TZID and no Z suffix given, assume local
     let naive_datetime_res = NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S");
     if let Ok(dt) = naive_datetime_res {
         // TODO: does this work?
@@ -170,9 +703,9 @@ pub struct Event {
     /// Matches CATEGORIES.
     pub categories: Option<Vec<String>>,
     /// Matches ATTENDEES.
-    pub attendees: Option<Vec<String>>,
+    pub attendees: Option<Vec<CalAddress>>,
     /// Matches ORGANIZER.
-    pub organizer: Option<String>,
+    pub organizer: Option<CalAddress>,
     /// Matches PRIORITY.
     pub priority: Option<u8>,
     /// Matches SEQUENCE.
@@ -187,8 +720,8 @@ pub struct Event {
     pub comment: Option<String>,
     /// Matches ATTACH.
     pub attach: Option<Vec<String>>,
-    /// Matches ALARMS.
-    pub alarms: Option<Vec<String>>,
+    /// Matches ALARMS, parsed from the nested `VALARM` subcomponents.
+    pub alarms: Option<Vec<Alarm>>,
     /// Matches LAST_MODIFIED.
     pub last_modified: Option<DateMaybeTime>,
 }
@@ -197,18 +730,377 @@ impl TryFrom<&IcalEvent> for Event {
     type Error = Error;
 
     fn try_from(value: &IcalEvent) -> Result<Self, Self::Error> {
-        map_ical_event(value)
+        map_ical_event(value, &[])
     }
 }
 impl TryFrom<IcalEvent> for Event {
     type Error = Error;
 
     fn try_from(value: IcalEvent) -> Result<Self, Self::Error> {
-        map_ical_event(&value)
+        map_ical_event(&value, &[])
+    }
+}
+
+impl Event {
+    /// Serialize this event back into a valid, folded `VEVENT` block
+    /// (including `BEGIN:VEVENT`/`END:VEVENT`), ready to be written
+    /// into an `.ics` file.
+    pub fn to_ical_string(&self) -> String {
+        let mut out = String::new();
+        out += &fold_line("BEGIN:VEVENT");
+
+        if let Some(uid) = &self.uid {
+            out += &fold_line(&format!("UID:{uid}"));
+        }
+        if let Some(created) = &self.created {
+            out += &fold_line(&created.to_ical_line("CREATED"));
+        }
+        if let Some(summary) = &self.summary {
+            out += &fold_line(&format!("SUMMARY:{}", escape_text(summary)));
+        }
+        if let Some(rrule) = &self.rrule {
+            for line in rrule.to_string().lines() {
+                out += &fold_line(line);
+            }
+        } else if let Some(start) = &self.start {
+            out += &fold_line(&start.to_ical_line("DTSTART"));
+        }
+        if let Some(end) = &self.end {
+            out += &fold_line(&end.to_ical_line("DTEND"));
+        }
+        if let Some(duration) = &self.duration {
+            out += &fold_line(&format!("DURATION:{}", format_duration(duration)));
+        }
+        if let Some(location) = &self.location {
+            out += &fold_line(&format!("LOCATION:{}", escape_text(location)));
+        }
+        if let Some(description) = &self.description {
+            out += &fold_line(&format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        if let Some(status) = &self.status {
+            let value = match status {
+                EventStatus::Tentative => "TENTATIVE",
+                EventStatus::Confirmed => "CONFIRMED",
+                EventStatus::Cancelled => "CANCELLED",
+            };
+            out += &fold_line(&format!("STATUS:{value}"));
+        }
+        if let Some(transparency) = &self.transparency {
+            let value = match transparency {
+                EventTransparency::Opaque => "OPAQUE",
+                EventTransparency::Transparent => "TRANSPARENT",
+            };
+            out += &fold_line(&format!("TRANSP:{value}"));
+        }
+        if let Some(categories) = &self.categories {
+            let joined = categories
+                .iter()
+                .map(|c| escape_text(c))
+                .collect::<Vec<_>>()
+                .join(",");
+            out += &fold_line(&format!("CATEGORIES:{joined}"));
+        }
+        if let Some(attendees) = &self.attendees {
+            for attendee in attendees {
+                out += &fold_line(&cal_address_to_ical_line("ATTENDEE", attendee));
+            }
+        }
+        if let Some(organizer) = &self.organizer {
+            out += &fold_line(&cal_address_to_ical_line("ORGANIZER", organizer));
+        }
+        if let Some(priority) = &self.priority {
+            out += &fold_line(&format!("PRIORITY:{priority}"));
+        }
+        if let Some(sequence) = &self.sequence {
+            out += &fold_line(&format!("SEQUENCE:{sequence}"));
+        }
+        if let Some(dtstamp) = &self.dtstamp {
+            out += &fold_line(&dtstamp.to_ical_line("DTSTAMP"));
+        }
+        if let Some(recurrence_id) = &self.recurrence_id {
+            out += &fold_line(&recurrence_id.to_ical_line("RECURRENCE-ID"));
+        }
+        if let Some(comment) = &self.comment {
+            out += &fold_line(&format!("COMMENT:{}", escape_text(comment)));
+        }
+        if let Some(attach) = &self.attach {
+            for attachment in attach {
+                out += &fold_line(&format!("ATTACH:{attachment}"));
+            }
+        }
+        if let Some(alarms) = &self.alarms {
+            for alarm in alarms {
+                out += &fold_line("BEGIN:VALARM");
+                out += &fold_line(&format!("ACTION:{}", alarm.action.as_ical_str()));
+                out += &fold_line(&alarm_trigger_to_ical_line(&alarm.trigger));
+                if let Some(description) = &alarm.description {
+                    out += &fold_line(&format!("DESCRIPTION:{}", escape_text(description)));
+                }
+                if let Some(repeat) = alarm.repeat {
+                    out += &fold_line(&format!("REPEAT:{repeat}"));
+                }
+                if let Some(repeat_interval) = &alarm.repeat_interval {
+                    out += &fold_line(&format!("DURATION:{}", format_duration(repeat_interval)));
+                }
+                out += &fold_line("END:VALARM");
+            }
+        }
+        if let Some(last_modified) = &self.last_modified {
+            out += &fold_line(&last_modified.to_ical_line("LAST-MODIFIED"));
+        }
+
+        out += &fold_line("END:VEVENT");
+        out
+    }
+}
+
+/// A single concrete occurrence of a (possibly recurring) event, as produced
+/// by [`Event::occurrences`] and [`expand_group`].
+#[derive(Debug, Clone)]
+pub struct Occurrence {
+    /// Start of this occurrence.
+    pub start: DateMaybeTime,
+    /// End of this occurrence.
+    pub end: DateMaybeTime,
+}
+
+impl Event {
+    /// Duration of a single occurrence: `self.duration` if present,
+    /// otherwise the `DTEND - DTSTART` delta of this event's own bounds.
+    pub(crate) fn occurrence_duration(&self) -> Option<Duration> {
+        if let Some(duration) = self.duration {
+            return Some(duration);
+        }
+        match (&self.start, &self.end) {
+            (Some(start), Some(end)) => Some(end.to_utc() - start.to_utc()),
+            _ => None,
+        }
+    }
+
+    /// Expand this event into its concrete occurrences whose start falls
+    /// within `[after, before)`. Recurring events are expanded via
+    /// `self.rrule`; a non-recurring event contributes at most its own
+    /// `self.start`.
+    pub fn occurrences(&self, after: DateTime<Utc>, before: DateTime<Utc>) -> Vec<Occurrence> {
+        let duration = self.occurrence_duration();
+        match &self.rrule {
+            Some(rrule) => rrule
+                .clone()
+                .into_iter()
+                .skip_while(|dt| dt.with_timezone(&Utc) < after)
+                .take_while(|dt| dt.with_timezone(&Utc) < before)
+                .map(|dt| {
+                    let start = dt.with_timezone(&Utc);
+                    let end = duration.map(|d| start + d).unwrap_or(start);
+                    Occurrence {
+                        start: start.into(),
+                        end: end.into(),
+                    }
+                })
+                .collect(),
+            None => match &self.start {
+                Some(start) if start.to_utc() >= after && start.to_utc() < before => {
+                    let end = duration
+                        .map(|d| start.to_utc() + d)
+                        .unwrap_or_else(|| start.to_utc());
+                    vec![Occurrence {
+                        start: start.clone(),
+                        end: end.into(),
+                    }]
+                }
+                _ => Vec::new(),
+            },
+        }
+    }
+}
+
+/// Merge a base recurring event with its `RECURRENCE-ID` overrides (a group
+/// of `VEVENT`s sharing one UID) into the occurrences that actually apply
+/// within `[after, before)`: an override replaces the instance whose
+/// original start matches its `recurrence_id`, or deletes it when the
+/// override's `STATUS` is `CANCELLED`.
+pub fn expand_group(
+    events: &[Event],
+    after: DateTime<Utc>,
+    before: DateTime<Utc>,
+) -> Vec<Occurrence> {
+    let base = events.iter().find(|event| event.recurrence_id.is_none());
+    let overrides = events.iter().filter(|event| event.recurrence_id.is_some());
+
+    let mut occurrences = base
+        .map(|base| base.occurrences(after, before))
+        .unwrap_or_default();
+
+    for over in overrides {
+        let Some(recurrence_id) = &over.recurrence_id else {
+            continue;
+        };
+        let recurrence_utc = recurrence_id.to_utc();
+        occurrences.retain(|occ| occ.start.to_utc() != recurrence_utc);
+
+        let cancelled = matches!(over.status, Some(EventStatus::Cancelled));
+        if !cancelled && recurrence_utc >= after && recurrence_utc < before {
+            let start = over.start.clone().unwrap_or_else(|| recurrence_id.clone());
+            let duration = over.occurrence_duration();
+            let end = duration
+                .map(|d| start.to_utc() + d)
+                .unwrap_or_else(|| start.to_utc());
+            occurrences.push(Occurrence {
+                start,
+                end: end.into(),
+            });
+        }
+    }
+
+    occurrences.sort_by_key(|occ| occ.start.to_utc());
+    occurrences
+}
+
+fn simple_property(name: &str, value: String) -> Property {
+    Property {
+        name: name.to_string(),
+        params: None,
+        value: Some(value),
+    }
+}
+
+fn date_property(name: &str, value: &DateMaybeTime) -> Property {
+    match value {
+        DateMaybeTime::Date(d) => Property {
+            name: name.to_string(),
+            params: Some(vec![("VALUE".to_string(), vec!["DATE".to_string()])]),
+            value: Some(d.format("%Y%m%d").to_string()),
+        },
+        DateMaybeTime::DateTime(dt) => Property {
+            name: name.to_string(),
+            params: None,
+            value: Some(dt.format("%Y%m%dT%H%M%SZ").to_string()),
+        },
+    }
+}
+
+impl TryFrom<&Event> for IcalEvent {
+    type Error = Error;
+
+    fn try_from(value: &Event) -> Result<Self, Self::Error> {
+        let mut properties = Vec::new();
+
+        if let Some(uid) = &value.uid {
+            properties.push(simple_property("UID", uid.clone()));
+        }
+        if let Some(created) = &value.created {
+            properties.push(date_property("CREATED", created));
+        }
+        if let Some(summary) = &value.summary {
+            properties.push(simple_property("SUMMARY", summary.clone()));
+        }
+        if let Some(rrule) = &value.rrule {
+            for line in rrule.to_string().lines() {
+                if let Some((key, val)) = line.split_once(':') {
+                    properties.push(simple_property(key, val.to_string()));
+                }
+            }
+        } else if let Some(start) = &value.start {
+            properties.push(date_property("DTSTART", start));
+        }
+        if let Some(end) = &value.end {
+            properties.push(date_property("DTEND", end));
+        }
+        if let Some(duration) = &value.duration {
+            properties.push(simple_property("DURATION", format_duration(duration)));
+        }
+        if let Some(location) = &value.location {
+            properties.push(simple_property("LOCATION", location.clone()));
+        }
+        if let Some(description) = &value.description {
+            properties.push(simple_property("DESCRIPTION", description.clone()));
+        }
+        if let Some(status) = &value.status {
+            let v = match status {
+                EventStatus::Tentative => "TENTATIVE",
+                EventStatus::Confirmed => "CONFIRMED",
+                EventStatus::Cancelled => "CANCELLED",
+            };
+            properties.push(simple_property("STATUS", v.to_string()));
+        }
+        if let Some(transparency) = &value.transparency {
+            let v = match transparency {
+                EventTransparency::Opaque => "OPAQUE",
+                EventTransparency::Transparent => "TRANSPARENT",
+            };
+            properties.push(simple_property("TRANSP", v.to_string()));
+        }
+        if let Some(categories) = &value.categories {
+            properties.push(simple_property("CATEGORIES", categories.join(",")));
+        }
+        if let Some(attendees) = &value.attendees {
+            for attendee in attendees {
+                properties.push(cal_address_property("ATTENDEE", attendee));
+            }
+        }
+        if let Some(organizer) = &value.organizer {
+            properties.push(cal_address_property("ORGANIZER", organizer));
+        }
+        if let Some(priority) = &value.priority {
+            properties.push(simple_property("PRIORITY", priority.to_string()));
+        }
+        if let Some(sequence) = &value.sequence {
+            properties.push(simple_property("SEQUENCE", sequence.to_string()));
+        }
+        if let Some(dtstamp) = &value.dtstamp {
+            properties.push(date_property("DTSTAMP", dtstamp));
+        }
+        if let Some(recurrence_id) = &value.recurrence_id {
+            properties.push(date_property("RECURRENCE-ID", recurrence_id));
+        }
+        if let Some(comment) = &value.comment {
+            properties.push(simple_property("COMMENT", comment.clone()));
+        }
+        if let Some(attach) = &value.attach {
+            for attachment in attach {
+                properties.push(simple_property("ATTACH", attachment.clone()));
+            }
+        }
+        if let Some(last_modified) = &value.last_modified {
+            properties.push(date_property("LAST-MODIFIED", last_modified));
+        }
+
+        let alarms = value
+            .alarms
+            .as_ref()
+            .map(|alarms| alarms.iter().map(alarm_to_ical).collect())
+            .unwrap_or_default();
+
+        Ok(IcalEvent { properties, alarms })
+    }
+}
+
+impl TryFrom<Event> for IcalEvent {
+    type Error = Error;
+
+    fn try_from(value: Event) -> Result<Self, Self::Error> {
+        IcalEvent::try_from(&value)
+    }
+}
+
+/// Rebuild a raw ical line (`KEY;TZID=...:VALUE`) for feeding into
+/// `RRuleSet`'s parser, carrying the `TZID` param along so a non-UTC
+/// `DTSTART`/`RDATE`/`EXDATE` isn't silently reinterpreted as UTC.
+fn ical_line_with_tzid(key: &str, value: &str, params: Option<&PropParams>) -> String {
+    match find_param(params, "TZID") {
+        Some(tzid) => format!("{key};TZID={tzid}:{value}"),
+        None => format!("{key}:{value}"),
     }
 }
 
-fn map_ical_event(input: &IcalEvent) -> Result<Event, Error> {
+/// Parse an [`IcalEvent`] into an [`Event`]. `tz_offsets` carries the
+/// enclosing [`Calendar`](crate::calendar::Calendar)'s in-file `VTIMEZONE`
+/// fallbacks (see [`parse_datetime`]); pass an empty slice when parsing a
+/// standalone event with no enclosing `VCALENDAR`.
+pub(crate) fn map_ical_event(
+    input: &IcalEvent,
+    tz_offsets: &[(String, i32)],
+) -> Result<Event, Error> {
     let mut event = Event::default();
     let mut rrule_lines: Option<Vec<_>> = None;
     let mut has_rrules = false;
@@ -219,39 +1111,72 @@ fn map_ical_event(input: &IcalEvent) -> Result<Event, Error> {
         let value = prop.value.as_ref().unwrap();
         let key: &str = &prop.name;
         if ["RDATE", "RRULE", "EXDATE", "EXRULE", "DTSTART"].contains(&key) {
-            rrule_lines.push(format!("{}:{}", key, value));
+            rrule_lines.push(ical_line_with_tzid(key, value, prop.params.as_ref()));
         }
         match key {
             "UID" => event.uid = Some(value.to_string()),
             "SUMMARY" => event.summary = Some(value.to_string()),
-            "DTSTART" => event.start = Some(parse_datetime(value.as_str())?),
-            "DTEND" => event.end = Some(parse_datetime(value.as_str())?),
-            "CREATED" => event.created = Some(parse_datetime(value.as_str())?),
+            "DTSTART" => {
+                event.start = Some(parse_datetime(
+                    value.as_str(),
+                    prop.params.as_ref(),
+                    tz_offsets,
+                )?)
+            }
+            "DTEND" => {
+                event.end = Some(parse_datetime(
+                    value.as_str(),
+                    prop.params.as_ref(),
+                    tz_offsets,
+                )?)
+            }
+            "CREATED" => {
+                event.created = Some(parse_datetime(
+                    value.as_str(),
+                    prop.params.as_ref(),
+                    tz_offsets,
+                )?)
+            }
             "DURATION" => event.duration = Some(parse_duration(value)?),
             "LOCATION" => event.location = Some(value.to_string()),
             "DESCRIPTION" => event.description = Some(value.to_string()),
             "STATUS" => event.status = Some(value.parse().map_err(|_| anyhow!("Invalid status"))?),
-            "LAST-MODIFIED" => event.last_modified = Some(parse_datetime(value)?),
+            "LAST-MODIFIED" => {
+                event.last_modified = Some(parse_datetime(value, prop.params.as_ref(), tz_offsets)?)
+            }
             "TRANSPARENCY" => {
                 event.transparency =
                     Some(value.parse().map_err(|_| anyhow!("Invalid transparency"))?)
             }
             "CATEGORIES" => event.categories.push(value.to_string()), // Push to OptionVector
-            "ATTENDEE" => event.attendees.push(value.to_string()),    // Push to OptionVector
-            "ORGANIZER" => event.organizer = Some(value.to_string()),
+            "ATTENDEE" => event
+                .attendees
+                .push(parse_cal_address(value, prop.params.as_ref())), // Push to OptionVector
+            "ORGANIZER" => event.organizer = Some(parse_cal_address(value, prop.params.as_ref())),
             "PRIORITY" => {
                 event.priority = Some(value.parse().map_err(|_| anyhow!("Invalid priority"))?)
             }
             "SEQUENCE" => {
                 event.sequence = Some(value.parse().map_err(|_| anyhow!("Invalid sequence"))?)
             }
-            "DTSTAMP" => event.dtstamp = Some(parse_datetime(value.as_str())?),
-            "RECURRENCE-ID" => event.recurrence_id = Some(parse_datetime(value.as_str())?),
+            "DTSTAMP" => {
+                event.dtstamp = Some(parse_datetime(
+                    value.as_str(),
+                    prop.params.as_ref(),
+                    tz_offsets,
+                )?)
+            }
+            "RECURRENCE-ID" => {
+                event.recurrence_id = Some(parse_datetime(
+                    value.as_str(),
+                    prop.params.as_ref(),
+                    tz_offsets,
+                )?)
+            }
             "RRULE" => has_rrules = true,
             "RDATE" | "EXRULE" | "EXDATE" => (),
             "COMMENT" => event.comment = Some(value.to_string()),
             "ATTACH" => event.attach.push(value.to_string()),
-            "ALARM" => event.alarms.push(value.to_string()),
             x if x.starts_with("X-") => (),
             "TRANSP" | "CLASS" => (),
             x => return Err(anyhow!("Unknown property key: {}", x)),
@@ -261,6 +1186,9 @@ fn map_ical_event(input: &IcalEvent) -> Result<Event, Error> {
         let rrule: RRuleSet = rrule_lines.unwrap().join("\n").parse()?;
         event.rrule = Some(rrule);
     }
+    for alarm in input.alarms.iter() {
+        event.alarms.push(parse_alarm(alarm, tz_offsets)?);
+    }
     Ok(event)
 }
 
@@ -279,7 +1207,7 @@ mod tests {
         for calendar in reader {
             let cal = calendar.unwrap();
             for event in cal.events {
-                let res = map_ical_event(&event);
+                let res = map_ical_event(&event, &[]);
                 let res = res.unwrap();
                 if res.summary == Some("Jeden Montag bis Freitag ganzt√§gig".into()) {
                     println!("{:#?}", res);
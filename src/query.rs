@@ -0,0 +1,115 @@
+//! CalDAV-style filtering of events, modeled after the `time-range` and
+//! `comp-filter` constructs from RFC 4791, so a server or sync tool can
+//! answer calendar-collection queries directly from the typed [`Event`]
+//! model instead of re-walking raw ical properties.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{DateMaybeTime, Event, EventStatus};
+
+/// The effective end of a non-recurring event: `DTEND` if present,
+/// otherwise `DTSTART + DURATION`, otherwise one day after `DTSTART` for a
+/// `VALUE=DATE` start, or `DTSTART` itself (zero duration) otherwise.
+fn effective_end(event: &Event, dtstart: &DateMaybeTime) -> DateTime<Utc> {
+    if let Some(end) = &event.end {
+        return end.to_utc();
+    }
+    if let Some(duration) = event.duration {
+        return dtstart.to_utc() + duration;
+    }
+    match dtstart {
+        DateMaybeTime::Date(_) => dtstart.to_utc() + Duration::days(1),
+        DateMaybeTime::DateTime(_) => dtstart.to_utc(),
+    }
+}
+
+/// Whether the interval `[occ_start, occ_end)` intersects `[start, end)`,
+/// per the RFC 4791 `time-range` overlap test. A zero-length instance
+/// (`occ_start == occ_end`) matches when `start <= occ_start < end`, since
+/// the strict `occ_end > start` test would otherwise never hold.
+fn interval_overlaps(
+    occ_start: DateTime<Utc>,
+    occ_end: DateTime<Utc>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> bool {
+    if occ_start == occ_end {
+        occ_start >= start && occ_start < end
+    } else {
+        occ_start < end && occ_end > start
+    }
+}
+
+/// Whether `event` has an occurrence intersecting `[start, end)`, per the
+/// RFC 4791 `time-range` overlap test.
+fn overlaps_time_range(event: &Event, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+    if event.rrule.is_some() {
+        // An occurrence can start before `start` and still overlap it, so
+        // widen the lookback by this event's own occurrence length before
+        // asking `Event::occurrences` (which filters by start instant
+        // alone), then check each candidate's full interval.
+        let lookback = event.occurrence_duration().unwrap_or(Duration::zero());
+        return event
+            .occurrences(start - lookback, end)
+            .into_iter()
+            .any(|occ| interval_overlaps(occ.start.to_utc(), occ.end.to_utc(), start, end));
+    }
+    let Some(dtstart) = &event.start else {
+        return false;
+    };
+    interval_overlaps(dtstart.to_utc(), effective_end(event, dtstart), start, end)
+}
+
+/// Filter `events` down to those with at least one occurrence intersecting
+/// `[start, end)`, implementing the RFC 4791 `time-range` filter.
+pub fn filter_time_range(
+    events: &[Event],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<&Event> {
+    events
+        .iter()
+        .filter(|event| overlaps_time_range(event, start, end))
+        .collect()
+}
+
+/// A `comp-filter`-like predicate on an event's properties, for queries
+/// such as "has an ATTENDEE" or "STATUS is CONFIRMED".
+#[derive(Debug)]
+pub enum PropertyFilter {
+    /// Matches events that have at least one `ATTENDEE`.
+    HasAttendee,
+    /// Matches events that have an `ORGANIZER`.
+    HasOrganizer,
+    /// Matches events that have at least one `VALARM`.
+    HasAlarm,
+    /// Matches events whose `STATUS` equals the given value.
+    Status(EventStatus),
+    /// Matches events listing the given value in `CATEGORIES`.
+    Category(String),
+}
+
+impl PropertyFilter {
+    /// Whether `event` satisfies this filter.
+    pub fn matches(&self, event: &Event) -> bool {
+        match self {
+            PropertyFilter::HasAttendee => event.attendees.as_ref().is_some_and(|a| !a.is_empty()),
+            PropertyFilter::HasOrganizer => event.organizer.is_some(),
+            PropertyFilter::HasAlarm => event.alarms.as_ref().is_some_and(|a| !a.is_empty()),
+            PropertyFilter::Status(status) => event.status.as_ref() == Some(status),
+            PropertyFilter::Category(category) => event
+                .categories
+                .as_ref()
+                .is_some_and(|categories| categories.iter().any(|c| c == category)),
+        }
+    }
+}
+
+/// Filter `events` down to those matching `filter`, implementing a
+/// `comp-filter`-like property query.
+pub fn comp_filter<'a>(events: &'a [Event], filter: &PropertyFilter) -> Vec<&'a Event> {
+    events
+        .iter()
+        .filter(|event| filter.matches(event))
+        .collect()
+}